@@ -1,10 +1,11 @@
 use std::{
     collections::HashMap,
     env,
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     net::{TcpListener, TcpStream},
     path::{Path, PathBuf},
     sync::Arc,
+    time::UNIX_EPOCH,
 };
 
 
@@ -75,31 +76,55 @@ enum FileType {
     NotFound,
 }
 
-fn handle_client(mut stream: TcpStream, current_dir: Arc<PathBuf>) {
-    let mut buffer = [0; 1024];
-    if let Err(e) = stream.read(&mut buffer) {
-        eprintln!("Error reading from stream: {}", e);
-        return;
-    }
+/// Maximum number of header bytes we're willing to buffer before giving up
+/// on a request; guards against a client that never sends `\r\n\r\n`.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: Option<String>,
+    headers: HashMap<String, String>,
+}
 
-    let request = String::from_utf8_lossy(&buffer[..]);
-    let map = request_parser(&request);
+/// Server-wide settings resolved once from command-line args and shared
+/// with every connection handler.
+struct ServerConfig {
+    root: PathBuf,
+    index_candidates: Vec<String>,
+    fallback_to_listing: bool,
+}
 
-    let default_path = "/".to_string();
-    let requested_path = map.get("Path").unwrap_or(&default_path);
+fn handle_client(mut stream: TcpStream, config: Arc<ServerConfig>) {
+    let raw_request = match read_request_head(&mut stream) {
+        Ok(raw_request) => raw_request,
+        Err(e) => {
+            eprintln!("Error reading from stream: {}", e);
+            return;
+        }
+    };
 
-    let mut path = current_dir.as_ref().clone();
-    if requested_path.starts_with('/') {
-        if let Some(stripped) = requested_path.strip_prefix('/') {
+    let request = String::from_utf8_lossy(&raw_request);
+    let req = request_parser(&request);
+    let is_head = req.method.eq_ignore_ascii_case("HEAD");
+
+    if !is_head && !req.method.eq_ignore_ascii_case("GET") {
+        send_method_not_allowed(&mut stream);
+        return;
+    }
+
+    let mut path = config.root.clone();
+    if req.path.starts_with('/') {
+        if let Some(stripped) = req.path.strip_prefix('/') {
             path.push(stripped);
         }
     } else {
-        path.push(requested_path);
+        path.push(&req.path);
     }
 
     let final_path = match path.canonicalize() {
         Ok(p) => {
-            if p.starts_with(current_dir.as_ref()) {
+            if p.starts_with(&config.root) {
                 p
             } else {
                 send_error_response(&mut stream, 403, "Forbidden");
@@ -114,27 +139,249 @@ fn handle_client(mut stream: TcpStream, current_dir: Arc<PathBuf>) {
 
     match check_is_file(&final_path) {
         FileType::Directory => {
-            let page = construct_response_page(&final_path, requested_path);
-            let response = format!(
-                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
-                page.len(),
-                page
-            );
-            if let Err(e) = stream.write_all(response.as_bytes()) {
-                eprintln!("Failed to write response: {}", e);
-            }
-        }
-        FileType::File => {
-            if let Err(e) = send_files_response(&final_path, &mut stream) {
-                eprintln!("Failed to send file response: {}", e);
+            match find_index_file(&final_path, &config.index_candidates) {
+                Some(index_path) => serve_file(&index_path, &req, is_head, &mut stream),
+                None if config.fallback_to_listing => {
+                    let page = construct_response_page(&final_path, &req.path);
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n",
+                        page.len()
+                    );
+                    let result = stream.write_all(head.as_bytes()).and_then(|_| {
+                        if is_head {
+                            Ok(())
+                        } else {
+                            stream.write_all(page.as_bytes())
+                        }
+                    });
+                    if let Err(e) = result {
+                        eprintln!("Failed to write response: {}", e);
+                    }
+                }
+                None => send_error_response(&mut stream, 404, "Not Found"),
             }
         }
+        FileType::File => serve_file(&final_path, &req, is_head, &mut stream),
         FileType::NotFound => {
             send_error_response(&mut stream, 404, "Not Found");
         }
     }
 }
 
+/// Returns the path to the first of `candidates` that exists as a regular
+/// file inside `dir`, in priority order.
+fn find_index_file(dir: &Path, candidates: &[String]) -> Option<PathBuf> {
+    candidates.iter().map(|name| dir.join(name)).find(|p| p.is_file())
+}
+
+/// Serves a single file at `file_path`, handling cache validators,
+/// conditional requests, and byte ranges. Shared by plain file requests and
+/// directory requests that resolve to an index file.
+fn serve_file(file_path: &Path, req: &HttpRequest, is_head: bool, stream: &mut TcpStream) {
+    let validators = match std::fs::metadata(file_path).and_then(Validators::from_metadata) {
+        Ok(validators) => validators,
+        Err(_) => {
+            send_error_response(stream, 404, "Not Found");
+            return;
+        }
+    };
+
+    if is_not_modified(&req.headers, &validators) {
+        send_not_modified(stream, &validators);
+        return;
+    }
+
+    let range = get_header(&req.headers, "Range");
+    let disposition = content_disposition(file_path, wants_download(req.query.as_deref()));
+    let meta = ResponseMeta {
+        content_type: content_type_for(file_path),
+        validators: &validators,
+        disposition: &disposition,
+    };
+    if let Err(e) = send_files_response(file_path, stream, range, !is_head, &meta) {
+        eprintln!("Failed to send file response: {}", e);
+    }
+}
+
+/// Reads from `stream` until the `\r\n\r\n` header terminator is seen (or
+/// the connection closes / the header grows past `MAX_HEADER_BYTES`),
+/// accumulating bytes across reads so request lines and headers spanning
+/// multiple TCP segments aren't truncated.
+fn read_request_head(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = [0; 1024];
+
+    loop {
+        let bytes_read = stream.read(&mut chunk)?;
+        if bytes_read == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..bytes_read]);
+
+        if data.windows(4).any(|w| w == b"\r\n\r\n") || data.len() >= MAX_HEADER_BYTES {
+            break;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Case-insensitive lookup into a parsed header map.
+fn get_header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Cache validators for a single file: a weak `ETag` derived from size and
+/// modification time, plus the same modification time formatted as an RFC
+/// 7231 IMF-fixdate for `Last-Modified`.
+struct Validators {
+    etag: String,
+    last_modified: String,
+    mtime_secs: u64,
+}
+
+impl Validators {
+    fn from_metadata(metadata: std::fs::Metadata) -> std::io::Result<Validators> {
+        let modified = metadata.modified()?;
+        let mtime_secs = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Ok(Validators {
+            etag: format!("W/\"{}-{}\"", metadata.len(), mtime_secs),
+            last_modified: format_http_date(mtime_secs),
+            mtime_secs,
+        })
+    }
+}
+
+/// Returns `true` when the request's `If-None-Match`/`If-Modified-Since`
+/// headers show the client already has the current version of the file.
+/// `If-None-Match` takes precedence over `If-Modified-Since`, per RFC 7232.
+fn is_not_modified(headers: &HashMap<String, String>, validators: &Validators) -> bool {
+    if let Some(if_none_match) = get_header(headers, "If-None-Match") {
+        return if_none_match.trim() == "*"
+            || if_none_match
+                .split(',')
+                .any(|candidate| candidate.trim() == validators.etag);
+    }
+
+    if let Some(if_modified_since) = get_header(headers, "If-Modified-Since") {
+        return parse_http_date(if_modified_since)
+            .map(|since_secs| validators.mtime_secs <= since_secs)
+            .unwrap_or(false);
+    }
+
+    false
+}
+
+fn send_not_modified(stream: &mut TcpStream, validators: &Validators) {
+    let response = format!(
+        "HTTP/1.1 304 Not Modified\r\nETag: {}\r\nLast-Modified: {}\r\nContent-Length: 0\r\n\r\n",
+        validators.etag, validators.last_modified
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        eprintln!("Failed to send 304 response: {}", e);
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Formats a Unix timestamp as an RFC 7231 IMF-fixdate, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn format_http_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time_of_day = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    let weekday = WEEKDAYS[weekday_from_days(days)];
+    let month_name = MONTHS[(month - 1) as usize];
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        weekday,
+        day,
+        month_name,
+        year,
+        time_of_day / 3600,
+        (time_of_day % 3600) / 60,
+        time_of_day % 60
+    )
+}
+
+/// Parses an RFC 7231 IMF-fixdate (the only form modern clients send) back
+/// into a Unix timestamp. Returns `None` on anything else.
+fn parse_http_date(date: &str) -> Option<u64> {
+    let mut fields = date.split_whitespace();
+    fields.next()?; // weekday, e.g. "Sun,"
+    let day: u32 = fields.next()?.parse().ok()?;
+    let month = fields.next()?;
+    let month = (MONTHS.iter().position(|&m| m == month)? as u32) + 1;
+    let year: i64 = fields.next()?.parse().ok()?;
+    let mut time_parts = fields.next()?.split(':');
+    let hour: u64 = time_parts.next()?.parse().ok()?;
+    let minute: u64 = time_parts.next()?.parse().ok()?;
+    let second: u64 = time_parts.next()?.parse().ok()?;
+
+    let days = days_from_civil(year, month, day);
+    Some((days * 86400) as u64 + hour * 3600 + minute * 60 + second)
+}
+
+/// Days since the Unix epoch for a given civil date, per Howard Hinnant's
+/// `days_from_civil` algorithm (proleptic Gregorian calendar).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Inverse of [`days_from_civil`]: civil `(year, month, day)` for a day
+/// count since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = if month <= 2 { y + 1 } else { y };
+    (year, month, day)
+}
+
+/// Day-of-week (0 = Sunday) for a day count since the Unix epoch.
+fn weekday_from_days(days: i64) -> usize {
+    (if days >= -4 {
+        (days + 4) % 7
+    } else {
+        (days + 5) % 7 + 6
+    }) as usize
+}
+
+fn send_method_not_allowed(stream: &mut TcpStream) {
+    let body = "<h1>405 Method Not Allowed</h1>";
+    let response = format!(
+        "HTTP/1.1 405 Method Not Allowed\r\nAllow: GET, HEAD\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        eprintln!("Failed to send 405 response: {}", e);
+    }
+}
+
 fn send_error_response(stream: &mut TcpStream, status_code: u16, reason: &str) {
     let response_body = format!("<h1>{} {}</h1>", status_code, reason);
     let response = format!(
@@ -149,15 +396,82 @@ fn send_error_response(stream: &mut TcpStream, status_code: u16, reason: &str) {
     }
 }
 
-fn request_parser(request: &str) -> HashMap<String, String> {
+fn request_parser(request: &str) -> HttpRequest {
+    let mut lines = request.lines();
+
+    let first_line = lines.next().unwrap_or("");
+    let mut parts = first_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let raw_path = parts.next().unwrap_or("/");
+    let (raw_path, query) = match raw_path.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (raw_path, None),
+    };
+    let path = urlencoding::decode(raw_path)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or_else(|_| raw_path.to_string());
+
     let mut headers = HashMap::new();
-    let first_line = request.lines().next().unwrap_or("");
-    if let Some(path) = first_line.split_whitespace().nth(1) {
-        if let Ok(decoded_path) = urlencoding::decode(path) {
-            headers.insert("Path".to_string(), decoded_path.into_owned());
+    for line in lines {
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_string(), value.trim().to_string());
         }
     }
-    headers
+
+    HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+    }
+}
+
+/// Returns `true` when the request's query string carries a `download`
+/// parameter (`?download` or `?download=...`), requesting a forced
+/// download instead of inline rendering.
+fn wants_download(query: Option<&str>) -> bool {
+    match query {
+        Some(query) => query
+            .split('&')
+            .any(|param| param == "download" || param.starts_with("download=")),
+        None => false,
+    }
+}
+
+/// Parses a `Range: bytes=...` value into an inclusive `(start, end)` byte
+/// range against a file of the given total length. Supports `N-` (offset to
+/// EOF), `N-M` (closed range) and `-S` (final `S` bytes). Only the first
+/// range in a comma-separated list is honored. Returns `None` when the
+/// range is malformed or unsatisfiable for `total`.
+fn parse_range(range_header: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = range_header.strip_prefix("bytes=")?;
+    let spec = spec.split(',').next()?.trim();
+    let (start_str, end_str) = spec.split_once('-')?;
+
+    if start_str.is_empty() {
+        let suffix_len: u64 = end_str.parse().ok()?;
+        if suffix_len == 0 || total == 0 {
+            return None;
+        }
+        Some((total.saturating_sub(suffix_len), total - 1))
+    } else {
+        let start: u64 = start_str.parse().ok()?;
+        if start >= total {
+            return None;
+        }
+        let end = if end_str.is_empty() {
+            total - 1
+        } else {
+            end_str.parse::<u64>().ok()?.min(total - 1)
+        };
+        if end < start {
+            return None;
+        }
+        Some((start, end))
+    }
 }
 
 fn check_is_file(path: &Path) -> FileType {
@@ -173,6 +487,35 @@ fn check_is_file(path: &Path) -> FileType {
     }
 }
 
+/// Percent-encodes each `/`-separated segment of a URL path so characters
+/// like `?`, `#` or spaces in a filename can't be misread as the start of
+/// a query string or fragment when the link is followed. Slashes between
+/// segments are preserved as path separators.
+fn encode_url_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Escapes `&`, `<`, `>`, `"` and `'` so untrusted text (e.g. a filename)
+/// can't break out of an HTML tag or attribute when interpolated into a
+/// generated page.
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 fn construct_response_page(path: &Path, received_path: &str) -> String {
     let files = fetch_all_files(path).unwrap_or_else(|e| {
         eprintln!("Error fetching files: {}", e);
@@ -189,16 +532,20 @@ fn construct_response_page(path: &Path, received_path: &str) -> String {
             a { text-decoration: none; color: #007aff; }
             .dir a { font-weight: bold; }
             .size { color: #888; font-size: 0.9em; text-align: right; }
+            .download { margin-left: 1em; color: #888; font-size: 0.9em; }
         </style></head><body>"
     );
 
-    page.push_str(&format!("<h1>Index of {}</h1><ul>", received_path));
+    page.push_str(&format!(
+        "<h1>Index of {}</h1><ul>",
+        html_escape(received_path)
+    ));
 
     if received_path != "/" {
         let parent_path = Path::new(received_path).parent().unwrap_or(Path::new("/")).to_str().unwrap_or("/");
         page.push_str(&format!(
             "<li class='dir'><a href='{}'>.. (Parent Directory)</a><span class='size'></span></li>",
-            parent_path
+            html_escape(&encode_url_path(parent_path))
         ));
     }
 
@@ -208,16 +555,26 @@ fn construct_response_page(path: &Path, received_path: &str) -> String {
         } else {
             format!("{}/{}", received_path, file.name)
         };
+        let link_path = html_escape(&encode_url_path(&link_path));
+        let name = html_escape(&file.name);
         let class = if file.isdir { "dir" } else { "file" };
         let size_info = if file.isdir {
             "&lt;DIR&gt;".to_string()
         } else {
             format!("{} bytes", file.size)
         };
+        let download_link = if file.isdir {
+            String::new()
+        } else {
+            format!(
+                "<a class='download' href='{}?download'>Download</a>",
+                link_path
+            )
+        };
 
         page.push_str(&format!(
-            "<li class='{}'><a href='{}'>{}</a><span class='size'>{}</span></li>",
-            class, link_path, file.name, size_info
+            "<li class='{}'><a href='{}'>{}</a>{}<span class='size'>{}</span></li>",
+            class, link_path, name, download_link, size_info
         ));
     }
 
@@ -249,49 +606,200 @@ fn fetch_all_files(path: &Path) -> Result<Vec<File>, std::io::Error> {
     Ok(files)
 }
 
-fn send_files_response(file_path: &Path, stream: &mut TcpStream) -> std::io::Result<()> {
+/// Per-response header values that stay constant across the small/large/
+/// range send paths, bundled together to keep their signatures manageable.
+struct ResponseMeta<'a> {
+    content_type: &'a str,
+    validators: &'a Validators,
+    disposition: &'a str,
+}
+
+fn send_files_response(
+    file_path: &Path,
+    stream: &mut TcpStream,
+    range_header: Option<&str>,
+    include_body: bool,
+    meta: &ResponseMeta,
+) -> std::io::Result<()> {
     let metadata = std::fs::metadata(file_path)?;
+
+    if let Some(range_header) = range_header {
+        return send_range_response(file_path, stream, range_header, metadata.len(), include_body, meta);
+    }
+
     if metadata.len() > 1024 * 1024 {
-        send_large_file_response(file_path, stream)
+        send_large_file_response(file_path, stream, include_body, meta)
     } else {
-        send_small_file_response(file_path, stream)
+        send_small_file_response(file_path, stream, include_body, meta)
+    }
+}
+
+/// Maps a file's extension (case-insensitive) to a MIME type, falling back
+/// to `application/octet-stream` for unknown or missing extensions.
+fn content_type_for(file_path: &Path) -> &'static str {
+    let extension = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
     }
 }
 
-fn send_small_file_response(file_path: &Path, stream: &mut TcpStream) -> std::io::Result<()> {
+/// Builds a `Content-Disposition` header value for `file_path`: `attachment`
+/// to force a download, `inline` to let the browser render it. The filename
+/// is carried both as an ASCII-safe quoted string and as an RFC 5987
+/// `filename*` (percent-encoded UTF-8) so names with spaces or non-ASCII
+/// characters survive intact.
+fn content_disposition(file_path: &Path, as_attachment: bool) -> String {
+    let name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("download");
+    let disposition = if as_attachment { "attachment" } else { "inline" };
+    let ascii_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_control() && c != '"' && c != '\\' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition,
+        ascii_name,
+        urlencoding::encode(name)
+    )
+}
+
+fn send_small_file_response(
+    file_path: &Path,
+    stream: &mut TcpStream,
+    include_body: bool,
+    meta: &ResponseMeta,
+) -> std::io::Result<()> {
+    let len = std::fs::metadata(file_path)?.len();
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nAccept-Ranges: bytes\r\nETag: {}\r\nLast-Modified: {}\r\nContent-Disposition: {}\r\nContent-Length: {}\r\n\r\n",
+        meta.content_type, meta.validators.etag, meta.validators.last_modified, meta.disposition, len
+    );
+    stream.write_all(response.as_bytes())?;
+
+    if !include_body {
+        return Ok(());
+    }
+
     let mut file = std::fs::File::open(file_path)?;
     let mut contents = Vec::new();
     file.read_to_end(&mut contents)?;
+    stream.write_all(&contents)
+}
+
+fn send_large_file_response(
+    file_path: &Path,
+    stream: &mut TcpStream,
+    include_body: bool,
+    meta: &ResponseMeta,
+) -> std::io::Result<()> {
+    let len = std::fs::metadata(file_path)?.len();
 
     let response = format!(
-        "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
-        contents.len()
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nAccept-Ranges: bytes\r\nETag: {}\r\nLast-Modified: {}\r\nContent-Disposition: {}\r\nContent-Length: {}\r\n\r\n",
+        meta.content_type, meta.validators.etag, meta.validators.last_modified, meta.disposition, len
     );
-
     stream.write_all(response.as_bytes())?;
-    stream.write_all(&contents)?;
-    Ok(())
-}
 
-fn send_large_file_response(file_path: &Path, stream: &mut TcpStream) -> std::io::Result<()> {
+    if !include_body {
+        return Ok(());
+    }
+
     let mut file = std::fs::File::open(file_path)?;
-    let len = file.metadata()?.len();
-    let chunk_size = 1024 * 1024;
-    let mut buffer = vec![0; chunk_size];
+    stream_file_chunks(&mut file, stream, len)
+}
+
+/// Serves a single byte range of `file_path` as `206 Partial Content`, or
+/// `416 Range Not Satisfiable` when `range_header` can't be satisfied
+/// against a file of `total` bytes. The selected range is streamed through
+/// the same 1 MiB chunked loop used for whole large files, so partial
+/// transfers of big files stay memory-bounded.
+fn send_range_response(
+    file_path: &Path,
+    stream: &mut TcpStream,
+    range_header: &str,
+    total: u64,
+    include_body: bool,
+    meta: &ResponseMeta,
+) -> std::io::Result<()> {
+    let Some((start, end)) = parse_range(range_header, total) else {
+        let response = format!(
+            "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\n\r\n",
+            total
+        );
+        return stream.write_all(response.as_bytes());
+    };
 
+    let length = end - start + 1;
     let response = format!(
-        "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
-        len
+        "HTTP/1.1 206 Partial Content\r\nContent-Type: {}\r\nAccept-Ranges: bytes\r\nETag: {}\r\nLast-Modified: {}\r\nContent-Disposition: {}\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\r\n",
+        meta.content_type,
+        meta.validators.etag,
+        meta.validators.last_modified,
+        meta.disposition,
+        start,
+        end,
+        total,
+        length
     );
-
     stream.write_all(response.as_bytes())?;
 
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
+    if !include_body {
+        return Ok(());
+    }
+
+    let mut file = std::fs::File::open(file_path)?;
+    file.seek(SeekFrom::Start(start))?;
+    stream_file_chunks(&mut file, stream, length)
+}
+
+/// Streams up to `remaining` bytes from `file`'s current position in 1 MiB
+/// chunks so large transfers don't have to be buffered in memory at once.
+fn stream_file_chunks(
+    file: &mut std::fs::File,
+    stream: &mut TcpStream,
+    mut remaining: u64,
+) -> std::io::Result<()> {
+    let chunk_size = 1024 * 1024;
+    let mut buffer = vec![0; chunk_size];
+
+    while remaining > 0 {
+        let to_read = remaining.min(chunk_size as u64) as usize;
+        let bytes_read = file.read(&mut buffer[..to_read])?;
         if bytes_read == 0 {
             break;
         }
         stream.write_all(&buffer[..bytes_read])?;
+        remaining -= bytes_read as u64;
     }
     Ok(())
 }
@@ -300,23 +808,42 @@ fn main() -> std::io::Result<()> {
     let args: Vec<String> = env::args().collect();
     let port = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(8123);
     let threads = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(4);
+    let index_candidates = args
+        .get(3)
+        .map(|s| s.split(',').map(str::to_string).collect())
+        .unwrap_or_else(|| {
+            vec![
+                "index.html".to_string(),
+                "index.htm".to_string(),
+                "index.txt".to_string(),
+            ]
+        });
+    let fallback_to_listing = args.get(4).and_then(|s| s.parse().ok()).unwrap_or(true);
 
     let address = format!("127.0.0.1:{}", port);
     let listener = TcpListener::bind(&address)?;
     let pool = thread_pool::ThreadPool::new(threads);
-    let current_dir = Arc::new(env::current_dir()?);
+    let config = Arc::new(ServerConfig {
+        root: env::current_dir()?,
+        index_candidates,
+        fallback_to_listing,
+    });
 
     println!("Server starting with {} threads.", threads);
-    println!("Serving files from: {}", current_dir.display());
+    println!("Serving files from: {}", config.root.display());
     println!("Listening on http://{}", address);
-
+    println!(
+        "Index candidates: {} (fallback to listing: {})",
+        config.index_candidates.join(", "),
+        config.fallback_to_listing
+    );
 
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
-                let current_dir_clone = Arc::clone(&current_dir);
+                let config_clone = Arc::clone(&config);
                 pool.execute(move || {
-                    handle_client(stream, current_dir_clone);
+                    handle_client(stream, config_clone);
                 });
             }
             Err(e) => {
@@ -328,3 +855,255 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_offset_to_eof() {
+        assert_eq!(parse_range("bytes=100-", 200), Some((100, 199)));
+    }
+
+    #[test]
+    fn parse_range_closed_range() {
+        assert_eq!(parse_range("bytes=0-99", 200), Some((0, 99)));
+    }
+
+    #[test]
+    fn parse_range_closed_range_clamped_to_eof() {
+        assert_eq!(parse_range("bytes=150-1000", 200), Some((150, 199)));
+    }
+
+    #[test]
+    fn parse_range_suffix_length() {
+        assert_eq!(parse_range("bytes=-50", 200), Some((150, 199)));
+    }
+
+    #[test]
+    fn parse_range_suffix_length_larger_than_file() {
+        assert_eq!(parse_range("bytes=-1000", 200), Some((0, 199)));
+    }
+
+    #[test]
+    fn parse_range_start_past_eof_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=200-", 200), None);
+    }
+
+    #[test]
+    fn parse_range_zero_length_suffix_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=-0", 200), None);
+    }
+
+    #[test]
+    fn parse_range_empty_file_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=0-", 0), None);
+    }
+
+    #[test]
+    fn parse_range_missing_prefix_is_malformed() {
+        assert_eq!(parse_range("0-99", 200), None);
+    }
+
+    #[test]
+    fn parse_range_only_first_of_multiple_ranges_is_honored() {
+        assert_eq!(parse_range("bytes=0-9,20-29", 200), Some((0, 9)));
+    }
+
+    #[test]
+    fn http_date_roundtrip_epoch() {
+        let formatted = format_http_date(0);
+        assert_eq!(formatted, "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(parse_http_date(&formatted), Some(0));
+    }
+
+    #[test]
+    fn http_date_roundtrip_arbitrary_timestamp() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let secs = 784_111_777u64;
+        let formatted = format_http_date(secs);
+        assert_eq!(formatted, "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date(&formatted), Some(secs));
+    }
+
+    #[test]
+    fn http_date_roundtrip_leap_day() {
+        // Leap day, end of day: Tue, 29 Feb 2000 23:59:59 GMT
+        let secs = 951_868_799u64;
+        let formatted = format_http_date(secs);
+        assert_eq!(formatted, "Tue, 29 Feb 2000 23:59:59 GMT");
+        assert_eq!(parse_http_date(&formatted), Some(secs));
+    }
+
+    #[test]
+    fn parse_http_date_rejects_garbage() {
+        assert_eq!(parse_http_date("not a date"), None);
+    }
+
+    #[test]
+    fn content_type_for_known_extensions() {
+        assert_eq!(content_type_for(Path::new("index.html")), "text/html");
+        assert_eq!(content_type_for(Path::new("archive.PDF")), "application/pdf");
+        assert_eq!(content_type_for(Path::new("clip.webm")), "video/webm");
+    }
+
+    #[test]
+    fn content_type_for_unknown_or_missing_extension_falls_back() {
+        assert_eq!(
+            content_type_for(Path::new("no_extension")),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            content_type_for(Path::new("file.unknownext")),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn content_disposition_inline_vs_attachment() {
+        let path = Path::new("report.pdf");
+        assert!(content_disposition(path, false).starts_with("inline;"));
+        assert!(content_disposition(path, true).starts_with("attachment;"));
+    }
+
+    #[test]
+    fn content_disposition_escapes_quotes_and_preserves_utf8_variant() {
+        let path = Path::new("caf\u{e9} notes\".txt");
+        let header = content_disposition(path, false);
+        assert!(!header.contains("notes\".txt\""));
+        assert!(header.contains("filename*=UTF-8''"));
+    }
+
+    #[test]
+    fn content_disposition_strips_crlf_header_injection() {
+        let path = Path::new("evil\r\nX-Injected: pwned");
+        let header = content_disposition(path, false);
+        assert!(!header.contains('\r'));
+        assert!(!header.contains('\n'));
+    }
+
+    #[test]
+    fn html_escape_escapes_special_characters() {
+        assert_eq!(
+            html_escape("<script>alert('x')&\"y\"</script>"),
+            "&lt;script&gt;alert(&#39;x&#39;)&amp;&quot;y&quot;&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn construct_response_page_escapes_html_tag_filename() {
+        // Filenames can't contain '/' on Unix, so the payload avoids a
+        // closing `</tag>` and instead breaks out via a bare `<img onerror=...>`.
+        let payload = "<img src=x onerror=alert(1)>";
+        let dir = std::env::temp_dir().join("file_server_test_html_escape_dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(payload), b"x").unwrap();
+
+        let page = construct_response_page(&dir, "/listdir");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!page.contains(payload));
+        assert!(page.contains("&lt;img src=x onerror=alert(1)&gt;"));
+    }
+
+    #[test]
+    fn request_parser_parses_method_path_query_and_headers() {
+        let raw = "GET /a/b?x=1&y=2 HTTP/1.1\r\nHost: example.com\r\nX-Custom: foo\r\nAccept: */*\r\n\r\n";
+        let req = request_parser(raw);
+
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.path, "/a/b");
+        assert_eq!(req.query.as_deref(), Some("x=1&y=2"));
+        assert_eq!(get_header(&req.headers, "host"), Some("example.com"));
+        assert_eq!(get_header(&req.headers, "x-custom"), Some("foo"));
+        assert_eq!(get_header(&req.headers, "accept"), Some("*/*"));
+    }
+
+    #[test]
+    fn read_request_head_accumulates_until_blank_line() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+
+        let (mut server_stream, _) = listener.accept().unwrap();
+        let head = read_request_head(&mut server_stream).unwrap();
+
+        assert_eq!(&head, b"GET / HTTP/1.1\r\nHost: x\r\n\r\n");
+    }
+
+    #[test]
+    fn handle_client_rejects_unsupported_method_with_405() {
+        let root = std::env::temp_dir().join("file_server_test_method_dispatch_root");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let config = Arc::new(ServerConfig {
+            root: root.clone(),
+            index_candidates: vec!["index.html".to_string()],
+            fallback_to_listing: true,
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"POST / HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+
+        let (server_stream, _) = listener.accept().unwrap();
+        handle_client(server_stream, config);
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed"));
+        assert!(response.contains("Allow: GET, HEAD"));
+    }
+
+    #[test]
+    fn find_index_file_honors_candidate_priority_order() {
+        let dir = std::env::temp_dir().join("file_server_test_find_index_priority");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.htm"), b"htm").unwrap();
+        std::fs::write(dir.join("index.txt"), b"txt").unwrap();
+
+        let candidates = vec![
+            "index.html".to_string(),
+            "index.htm".to_string(),
+            "index.txt".to_string(),
+        ];
+        let found = find_index_file(&dir, &candidates);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, Some(dir.join("index.htm")));
+    }
+
+    #[test]
+    fn find_index_file_returns_none_when_no_candidate_exists() {
+        let dir = std::env::temp_dir().join("file_server_test_find_index_missing");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let candidates = vec!["index.html".to_string()];
+        let found = find_index_file(&dir, &candidates);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn wants_download_detects_bare_and_valued_param() {
+        assert!(wants_download(Some("download")));
+        assert!(wants_download(Some("download=1")));
+        assert!(wants_download(Some("a=1&download")));
+        assert!(!wants_download(Some("a=1&b=2")));
+        assert!(!wants_download(None));
+    }
+}
+